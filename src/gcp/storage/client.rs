@@ -4,49 +4,379 @@ use crate::gcp::{
     Client,
 };
 use futures::{Stream, TryStream, TryStreamExt};
+use reqwest::Url;
 use serde::{de::DeserializeOwned, Serialize};
 use tokio::sync::RwLock;
 
+/// Default origin used for all storage operations.
+pub(super) const DEFAULT_ENDPOINT: &str = "https://storage.googleapis.com";
+
+/// Controls automatic retries of transient GCS failures (`408`, `429` and `5xx`).
+///
+/// The delay between attempts is full-jittered exponential backoff,
+/// `rand * min(cap, base * 2^attempt)`, unless the response carries a
+/// `Retry-After` header, which is honored verbatim.
+#[derive(Debug, Clone)]
+pub(super) struct RetryPolicy {
+    max_attempts: u32,
+    base: std::time::Duration,
+    cap: std::time::Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base: std::time::Duration::from_millis(500),
+            cap: std::time::Duration::from_secs(32),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Builds a policy with a custom attempt ceiling and backoff bounds. Prefer
+    /// [`RetryPolicy::default`] unless you need to tune these.
+    pub(super) fn new(
+        max_attempts: u32,
+        base: std::time::Duration,
+        cap: std::time::Duration,
+    ) -> Self {
+        Self {
+            max_attempts,
+            base,
+            cap,
+        }
+    }
+
+    fn is_retryable(status: reqwest::StatusCode) -> bool {
+        status == reqwest::StatusCode::REQUEST_TIMEOUT
+            || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || status.is_server_error()
+    }
+
+    /// Full-jitter exponential backoff for the given zero-based attempt index.
+    fn backoff(&self, attempt: u32) -> std::time::Duration {
+        let exp = self.base.saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX));
+        let capped = std::cmp::min(exp, self.cap);
+        capped.mul_f64(rand::random::<f64>())
+    }
+
+    /// Parses a `Retry-After` header expressed either as a number of seconds or
+    /// as an HTTP-date, returning the delay relative to now.
+    fn retry_after(response: &reqwest::Response) -> Option<std::time::Duration> {
+        let raw = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)?
+            .to_str()
+            .ok()?;
+        Self::parse_retry_after(raw, std::time::SystemTime::now())
+    }
+
+    /// Pure `Retry-After` parser: a bare integer is seconds, otherwise an
+    /// HTTP-date whose delay is measured against `now`. A date in the past yields
+    /// `None`, falling back to the computed backoff.
+    fn parse_retry_after(raw: &str, now: std::time::SystemTime) -> Option<std::time::Duration> {
+        if let Ok(secs) = raw.trim().parse::<u64>() {
+            return Some(std::time::Duration::from_secs(secs));
+        }
+        let when = httpdate::parse_http_date(raw).ok()?;
+        when.duration_since(now).ok()
+    }
+}
+
+/// Storage class applied to a newly inserted object.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub(super) enum StorageClass {
+    #[serde(rename = "STANDARD")]
+    Standard,
+    #[serde(rename = "NEARLINE")]
+    Nearline,
+    #[serde(rename = "COLDLINE")]
+    Coldline,
+    #[serde(rename = "ARCHIVE")]
+    Archive,
+}
+
+/// Predefined ACL applied to a newly inserted object, mapped to the
+/// `predefinedAcl` upload query parameter.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum PredefinedAcl {
+    Private,
+    PublicRead,
+    PublicReadWrite,
+    ProjectPrivate,
+    AuthenticatedRead,
+    BucketOwnerRead,
+    BucketOwnerFullControl,
+}
+
+impl PredefinedAcl {
+    fn as_param(self) -> &'static str {
+        match self {
+            Self::Private => "private",
+            Self::PublicRead => "publicRead",
+            Self::PublicReadWrite => "publicReadWrite",
+            Self::ProjectPrivate => "projectPrivate",
+            Self::AuthenticatedRead => "authenticatedRead",
+            Self::BucketOwnerRead => "bucketOwnerRead",
+            Self::BucketOwnerFullControl => "bucketOwnerFullControl",
+        }
+    }
+}
+
+/// Optional attributes set on an object at insert time. Empty by default, in
+/// which case GCS applies its own defaults (octet-stream content-type, the
+/// bucket's storage class, no custom metadata, bucket-default ACL).
+#[derive(Debug, Clone, Default)]
+pub(super) struct InsertObjectOptions {
+    pub content_type: Option<String>,
+    pub metadata: std::collections::BTreeMap<String, String>,
+    pub storage_class: Option<StorageClass>,
+    pub predefined_acl: Option<PredefinedAcl>,
+}
+
+impl InsertObjectOptions {
+    /// Applies the content-type as a request header, for the simple-upload path.
+    /// Only the content-type survives a `uploadType=media` request; metadata and
+    /// storage class need a JSON metadata part and are rejected up front by
+    /// [`Self::ensure_simple_upload_supported`].
+    fn apply_headers(&self, mut builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if let Some(content_type) = &self.content_type {
+            builder = builder.header(reqwest::header::CONTENT_TYPE, content_type);
+        }
+        builder
+    }
+
+    /// Fails when the options carry attributes a simple `uploadType=media` request
+    /// cannot express (user metadata, storage class). Rather than silently produce
+    /// an object with the wrong attributes, callers needing these must use the
+    /// resumable path, which sends a JSON metadata part.
+    fn ensure_simple_upload_supported(&self) -> StorageResult<()> {
+        if self.storage_class.is_some() || !self.metadata.is_empty() {
+            return Err(super::Error::gcs_unexpected_response_error(
+                "post",
+                "metadata and storage class require the resumable upload path; \
+                 use post_resumable for objects that set them"
+                    .to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Appends the `predefinedAcl` query parameter when set.
+    fn apply_query(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.predefined_acl {
+            Some(acl) => builder.query(&[("predefinedAcl", acl.as_param())]),
+            None => builder,
+        }
+    }
+
+    /// Builds the JSON object-metadata part sent when initiating a resumable
+    /// upload, or `None` when no attribute needs a metadata part.
+    fn metadata_json(&self) -> Option<serde_json::Value> {
+        let mut map = serde_json::Map::new();
+        if let Some(content_type) = &self.content_type {
+            map.insert("contentType".to_owned(), serde_json::json!(content_type));
+        }
+        if !self.metadata.is_empty() {
+            map.insert("metadata".to_owned(), serde_json::json!(self.metadata));
+        }
+        if let Some(storage_class) = self.storage_class {
+            map.insert("storageClass".to_owned(), serde_json::json!(storage_class));
+        }
+        (!map.is_empty()).then(|| serde_json::Value::Object(map))
+    }
+}
+
+/// Tunables for the underlying [`reqwest::Client`] used for both token fetches
+/// and storage operations. Defaults match `reqwest`'s own defaults (no proxy,
+/// platform TLS roots, no explicit timeouts, `reqwest`'s redirect policy).
+#[derive(Debug, Default)]
+pub(super) struct TransportConfig {
+    /// HTTP/HTTPS proxy URL applied to all schemes.
+    pub proxy: Option<String>,
+    /// Load the operating system's trust store in addition to any supplied PEMs.
+    pub use_native_certs: bool,
+    /// Additional root certificates (PEM encoded) for private CAs.
+    pub extra_ca_certs: Vec<Vec<u8>>,
+    /// Overall per-request timeout.
+    pub timeout: Option<std::time::Duration>,
+    /// Connection-establishment timeout.
+    pub connect_timeout: Option<std::time::Duration>,
+    /// Maximum number of redirects to follow; `None` keeps the default policy.
+    pub max_redirects: Option<usize>,
+}
+
+impl TransportConfig {
+    fn build(&self) -> StorageResult<reqwest::Client> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy).map_err(super::Error::GcsHttpError)?;
+            builder = builder.proxy(proxy);
+        }
+
+        if self.use_native_certs {
+            for cert in rustls_native_certs::load_native_certs().map_err(|err| {
+                super::Error::gcs_unexpected_response_error("native-certs", err.to_string())
+            })? {
+                // `rustls_native_certs::Certificate` is a newtype over the DER
+                // bytes; hand the inner `Vec<u8>` to reqwest.
+                let cert = reqwest::Certificate::from_der(&cert.0)
+                    .map_err(super::Error::GcsHttpError)?;
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        for pem in &self.extra_ca_certs {
+            let cert = reqwest::Certificate::from_pem(pem).map_err(super::Error::GcsHttpError)?;
+            builder = builder.add_root_certificate(cert);
+        }
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(max) = self.max_redirects {
+            builder = builder.redirect(reqwest::redirect::Policy::limited(max));
+        }
+
+        builder.build().map_err(super::Error::GcsHttpError)
+    }
+}
+
 #[derive(Debug)]
 pub(super) struct StorageClient<T> {
     client: Client,
     token_generator: T,
-    token: RwLock<Token>,
+    token: RwLock<Option<Token>>,
+    endpoint: Url,
+    skip_auth: bool,
+    retry: RetryPolicy,
 }
 
 impl<T: TokenGenerator> StorageClient<T> {
     pub async fn new(token_generator: T) -> StorageResult<Self> {
-        let client = Client::default();
-        let token = token_generator
-            .get(&client)
-            .await
-            .map_err(Error::GcsTokenError)?;
+        Self::new_with_endpoint(token_generator, DEFAULT_ENDPOINT).await
+    }
+
+    /// Builds a client that routes every operation through `endpoint` instead of
+    /// the public googleapis origin. Use this to target a private API mirror or a
+    /// local emulator such as `fake-gcs-server`. When the endpoint points at an
+    /// emulator that performs no authentication, use
+    /// [`Self::new_with_endpoint_unauthenticated`] instead, which also skips the
+    /// eager token fetch so no live credentials are needed.
+    pub async fn new_with_endpoint(
+        token_generator: T,
+        endpoint: &str,
+    ) -> StorageResult<Self> {
+        Self::from_client(token_generator, endpoint, Client::default(), false).await
+    }
+
+    /// Builds a client for an unauthenticated emulator (e.g. `fake-gcs-server`).
+    /// No bearer token is ever injected and, crucially, the eager token fetch is
+    /// skipped — so this works without live credentials, which is the whole point
+    /// of targeting an emulator in tests. The `token_generator` is still held but
+    /// never consulted.
+    pub async fn new_with_endpoint_unauthenticated(
+        token_generator: T,
+        endpoint: &str,
+    ) -> StorageResult<Self> {
+        Self::from_client(token_generator, endpoint, Client::default(), true).await
+    }
+
+    /// Builds a client whose transport is tuned by `transport` (proxy, custom CA
+    /// roots, timeouts, redirect policy). The same [`reqwest::Client`] backs both
+    /// token fetches and every storage operation.
+    ///
+    /// Set `skip_auth` to target an unauthenticated emulator with a tuned
+    /// transport; it also skips the eager token fetch, just like
+    /// [`Self::new_with_endpoint_unauthenticated`].
+    pub async fn new_with_transport(
+        token_generator: T,
+        endpoint: &str,
+        transport: TransportConfig,
+        skip_auth: bool,
+    ) -> StorageResult<Self> {
+        let client = Client::new(transport.build()?);
+        Self::from_client(token_generator, endpoint, client, skip_auth).await
+    }
+
+    async fn from_client(
+        token_generator: T,
+        endpoint: &str,
+        client: Client,
+        skip_auth: bool,
+    ) -> StorageResult<Self> {
+        let endpoint = Url::parse(endpoint)
+            .map_err(|err| Error::gcs_unexpected_response_error(endpoint, err.to_string()))?;
+        // Only fetch a token eagerly when the endpoint actually authenticates;
+        // an emulator target must never require live credentials to construct.
+        let token = if skip_auth {
+            None
+        } else {
+            Some(
+                token_generator
+                    .get(&client)
+                    .await
+                    .map_err(Error::GcsTokenError)?,
+            )
+        };
         Ok(Self {
             client,
             token_generator,
             token: RwLock::new(token),
+            endpoint,
+            skip_auth,
+            retry: RetryPolicy::default(),
         })
     }
 
+    /// Overrides the default [`RetryPolicy`] for transient failures.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Rewrites the scheme/host/port of `url` to point at the configured
+    /// [`endpoint`](Self::endpoint), leaving the path and query untouched. When the
+    /// endpoint is the default googleapis origin this is a cheap no-op clone.
+    fn endpoint_url(&self, url: &str) -> StorageResult<Url> {
+        let mut parsed = Url::parse(url)
+            .map_err(|err| Error::gcs_unexpected_response_error(url, err.to_string()))?;
+        let _ = parsed.set_scheme(self.endpoint.scheme());
+        parsed
+            .set_host(self.endpoint.host_str())
+            .map_err(|err| Error::gcs_unexpected_response_error(url, err.to_string()))?;
+        let _ = parsed.set_port(self.endpoint.port());
+        Ok(parsed)
+    }
+
     async fn refresh_token(&self) -> StorageResult<AccessToken> {
-        let t = self.token.read().await;
-        if t.is_valid() {
-            Ok(t.access_token())
-        } else {
-            let t = self
-                .token_generator
-                .get(&self.client)
-                .await
-                .map_err(Error::GcsTokenError)?;
-            let access_token = t.access_token();
-            *self.token.write().await = t;
-            Ok(access_token)
+        if let Some(t) = self.token.read().await.as_ref() {
+            if t.is_valid() {
+                return Ok(t.access_token());
+            }
         }
+        let t = self
+            .token_generator
+            .get(&self.client)
+            .await
+            .map_err(Error::GcsTokenError)?;
+        let access_token = t.access_token();
+        *self.token.write().await = Some(t);
+        Ok(access_token)
     }
 
-    async fn success_response(
+    /// Turns the final `response` into a success or a terminal error. When the
+    /// request was retried (`attempts > 1`) the attempt count is folded into the
+    /// error message so callers can tell a flaky endpoint from a hard failure.
+    async fn finalize(
         url: &str,
         response: reqwest::Response,
+        attempts: u32,
     ) -> StorageResult<reqwest::Response> {
         let status = response.status();
         if status.is_success() {
@@ -59,83 +389,325 @@ impl<T: TokenGenerator> StorageClient<T> {
             });
         }
 
-        let err = response.text().await.map_err(super::Error::GcsHttpError)?;
+        let body = response.text().await.map_err(super::Error::GcsHttpError)?;
+        let err = if attempts > 1 {
+            format!("after {attempts} attempts: {body}")
+        } else {
+            body
+        };
         Err(super::Error::gcs_unexpected_response_error(url, err))
     }
 
-    pub async fn delete(&self, url: &str) -> StorageResult<()> {
-        let response = self
-            .client
-            .client
-            .delete(url)
-            .bearer_auth(self.refresh_token().await?)
-            .send()
-            .await
-            .map_err(super::Error::GcsHttpError)?;
-        Self::success_response(url, response).await?;
+    /// Sends the request produced by `make` (a fresh [`reqwest::RequestBuilder`]
+    /// per attempt so the body stream can be re-created), retrying transient
+    /// failures according to the configured [`RetryPolicy`] before finalizing.
+    async fn send_with_retry<F>(&self, url: &str, make: F) -> StorageResult<reqwest::Response>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let mut attempt: u32 = 0;
+        loop {
+            let response = self
+                .authorize(make())
+                .await?
+                .send()
+                .await
+                .map_err(super::Error::GcsHttpError)?;
+
+            let is_last = attempt + 1 >= self.retry.max_attempts;
+            if !is_last && RetryPolicy::is_retryable(response.status()) {
+                let delay = RetryPolicy::retry_after(&response)
+                    .unwrap_or_else(|| self.retry.backoff(attempt));
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+
+            return Self::finalize(url, response, attempt + 1).await;
+        }
+    }
+
+    /// Injects the bearer token unless the client is configured against an
+    /// unauthenticated emulator (see [`Self::new_with_endpoint_unauthenticated`]).
+    async fn authorize(
+        &self,
+        builder: reqwest::RequestBuilder,
+    ) -> StorageResult<reqwest::RequestBuilder> {
+        if self.skip_auth {
+            Ok(builder)
+        } else {
+            Ok(builder.bearer_auth(self.refresh_token().await?))
+        }
+    }
+
+    pub async fn delete(&self, object: &ObjectId) -> StorageResult<()> {
+        let url = object.url();
+        let target = self.endpoint_url(&url)?;
+        self.send_with_retry(&url, || self.client.client.delete(target.clone()))
+            .await?;
         Ok(())
     }
 
-    pub async fn post<S>(&self, url: &str, body: S) -> StorageResult<()>
+    /// Uploads `body` with a single simple-upload request, applying the `options`
+    /// content-type header and `predefinedAcl` query parameter. Options that a
+    /// `uploadType=media` request cannot express (user metadata, storage class)
+    /// are rejected rather than silently dropped — use [`Self::post_resumable`]
+    /// for those. Because the request is retried on transient failures, the body
+    /// is supplied as a factory that yields a fresh stream per attempt rather than
+    /// a one-shot stream.
+    pub async fn post<F, S>(
+        &self,
+        url: &str,
+        body: F,
+        options: &InsertObjectOptions,
+    ) -> StorageResult<()>
     where
+        F: Fn() -> S,
         S: TryStream + Send + Sync + 'static,
         S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
         bytes::Bytes: From<S::Ok>,
     {
+        options.ensure_simple_upload_supported()?;
+        let target = self.endpoint_url(url)?;
+        self.send_with_retry(url, || {
+            let builder = self.client.client.post(target.clone());
+            let builder = options.apply_query(options.apply_headers(builder));
+            builder.body(reqwest::Body::wrap_stream(body()))
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Uploads `body` using the GCS resumable protocol, splitting it into chunks
+    /// that are multiples of 256 KiB. After each chunk the committed offset
+    /// reported by GCS (via the `Range` header on a `308`) is checked against the
+    /// bytes sent. A chunk that GCS commits only in part, or a chunk request that
+    /// fails, ends the upload with an error; the chunk is not re-sent and the
+    /// upload does not continue from the committed offset. The session can be
+    /// inspected afterwards with [`Self::resumable_committed`]. `chunk_size` is
+    /// rounded down to a 256 KiB multiple (and never below 256 KiB).
+    pub async fn post_resumable<S>(
+        &self,
+        url: &str,
+        body: S,
+        chunk_size: usize,
+        options: &InsertObjectOptions,
+    ) -> StorageResult<()>
+    where
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        bytes::Bytes: From<S::Ok>,
+    {
+        const MIN_CHUNK: usize = 256 * 1024;
+        let chunk_size = std::cmp::max(chunk_size - (chunk_size % MIN_CHUNK), MIN_CHUNK);
+
+        let session_uri = self.initiate_resumable(url, options).await?;
+
+        let mut body = Box::pin(body.into_stream());
+        let mut buf = bytes::BytesMut::new();
+        let mut offset: u64 = 0;
+
+        loop {
+            match body
+                .try_next()
+                .await
+                .map_err(|err| super::Error::gcs_unexpected_response_error(url, err.into().to_string()))?
+            {
+                Some(chunk) => {
+                    buf.extend_from_slice(&bytes::Bytes::from(chunk));
+                    while buf.len() >= 2 * chunk_size {
+                        let chunk = buf.split_to(chunk_size).freeze();
+                        offset = self
+                            .put_chunk(&session_uri, url, chunk, offset, None)
+                            .await?;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        // End of stream: flush the remainder, carrying the now-known total length.
+        let total = offset + buf.len() as u64;
+        while buf.len() > chunk_size {
+            let chunk = buf.split_to(chunk_size).freeze();
+            offset = self
+                .put_chunk(&session_uri, url, chunk, offset, Some(total))
+                .await?;
+        }
+        let chunk = buf.split().freeze();
+        self.put_chunk(&session_uri, url, chunk, offset, Some(total))
+            .await?;
+        Ok(())
+    }
+
+    /// Issues the resumable upload initiation request and returns the session URI
+    /// handed back in the `Location` response header. `options` are sent as a JSON
+    /// object-metadata body (content-type, user metadata, storage class) plus the
+    /// `X-Upload-Content-Type` header and `predefinedAcl` query parameter.
+    async fn initiate_resumable(
+        &self,
+        url: &str,
+        options: &InsertObjectOptions,
+    ) -> StorageResult<String> {
+        let target = self.endpoint_url(url)?;
+        let metadata = options.metadata_json();
+        let response = self
+            .send_with_retry(url, || {
+                let mut builder = self
+                    .client
+                    .client
+                    .post(target.clone())
+                    .query(&[("uploadType", "resumable")]);
+                builder = options.apply_query(builder);
+                if let Some(content_type) = &options.content_type {
+                    builder = builder.header("X-Upload-Content-Type", content_type);
+                }
+                match &metadata {
+                    Some(metadata) => builder.json(metadata),
+                    None => builder.header(reqwest::header::CONTENT_LENGTH, 0),
+                }
+            })
+            .await?;
+
+        response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_owned())
+            .ok_or_else(|| {
+                super::Error::gcs_unexpected_response_error(
+                    url,
+                    "resumable upload initiation returned no Location header".to_owned(),
+                )
+            })
+    }
+
+    /// PUTs a single chunk to `session_uri` with the appropriate `Content-Range`
+    /// header and returns the number of bytes GCS has now committed. A `308`
+    /// ("resume incomplete") is parsed from the `Range` header; a `200`/`201`
+    /// completes the object.
+    async fn put_chunk(
+        &self,
+        session_uri: &str,
+        url: &str,
+        chunk: bytes::Bytes,
+        offset: u64,
+        total: Option<u64>,
+    ) -> StorageResult<u64> {
+        let len = chunk.len() as u64;
+        let content_range = match (len, total) {
+            (0, Some(total)) => format!("bytes */{total}"),
+            (_, Some(total)) => format!("bytes {}-{}/{}", offset, offset + len - 1, total),
+            (_, None) => format!("bytes {}-{}/*", offset, offset + len - 1),
+        };
+
         let response = self
-            .client
-            .client
-            .post(url)
-            .bearer_auth(self.refresh_token().await?)
-            .body(reqwest::Body::wrap_stream(body))
+            .authorize(
+                self.client
+                    .client
+                    .put(session_uri)
+                    .header(reqwest::header::CONTENT_RANGE, content_range)
+                    .body(chunk),
+            )
+            .await?
             .send()
             .await
             .map_err(super::Error::GcsHttpError)?;
 
-        Self::success_response(url, response).await?;
-        Ok(())
+        if response.status().as_u16() == 308 {
+            // We sent `len` bytes starting at `offset`, so GCS must report exactly
+            // `offset + len` committed. A missing `Range` header means nothing is
+            // committed. Any other value is a partial commit: the uncommitted tail
+            // is not re-sent here, so fail loudly rather than silently corrupt the
+            // object by continuing from a desynchronized offset.
+            let expected = offset + len;
+            let committed = Self::parse_committed(&response).unwrap_or(0);
+            if committed != expected {
+                return Err(super::Error::gcs_unexpected_response_error(
+                    url,
+                    format!(
+                        "resumable chunk partially committed: expected {expected} bytes, \
+                         GCS committed {committed}"
+                    ),
+                ));
+            }
+            Ok(committed)
+        } else {
+            Self::finalize(url, response, 1).await?;
+            Ok(total.unwrap_or(offset + len))
+        }
+    }
+
+    /// Parses the committed byte count from a `Range: bytes=0-<last>` header.
+    fn parse_committed(response: &reqwest::Response) -> Option<u64> {
+        let range = response
+            .headers()
+            .get(reqwest::header::RANGE)?
+            .to_str()
+            .ok()?;
+        let last = range.rsplit('-').next()?.trim();
+        last.parse::<u64>().ok().map(|last| last + 1)
+    }
+
+    /// Queries an interrupted resumable session for how many bytes GCS has
+    /// committed, sending a zero-length `Content-Range: bytes */<total>`. Returns
+    /// `None` when the object is already complete.
+    pub async fn resumable_committed(
+        &self,
+        session_uri: &str,
+        total: u64,
+    ) -> StorageResult<Option<u64>> {
+        let response = self
+            .authorize(
+                self.client
+                    .client
+                    .put(session_uri)
+                    .header(reqwest::header::CONTENT_RANGE, format!("bytes */{total}"))
+                    .header(reqwest::header::CONTENT_LENGTH, 0),
+            )
+            .await?
+            .send()
+            .await
+            .map_err(super::Error::GcsHttpError)?;
+
+        if response.status().as_u16() == 308 {
+            Ok(Some(Self::parse_committed(&response).unwrap_or(0)))
+        } else {
+            Self::finalize(session_uri, response, 1).await?;
+            Ok(None)
+        }
     }
 
     pub async fn get_as_stream<Q>(
         &self,
-        url: &str,
+        object: &ObjectId,
         query: &Q,
     ) -> StorageResult<impl Stream<Item = StorageResult<bytes::Bytes>>>
     where
         Q: Serialize,
     {
+        let url = object.url();
+        let target = self.endpoint_url(&url)?;
         let response = self
-            .client
-            .client
-            .get(url)
-            .bearer_auth(self.refresh_token().await?)
-            .query(query)
-            .send()
-            .await
-            .map_err(super::Error::GcsHttpError)?;
+            .send_with_retry(&url, || self.client.client.get(target.clone()).query(query))
+            .await?;
 
-        Ok(Self::success_response(url, response)
-            .await?
+        Ok(response
             .bytes_stream()
             .map_err(super::Error::GcsHttpError))
     }
 
-    pub async fn get_as_json<R, Q>(&self, url: &str, query: &Q) -> StorageResult<R>
+    pub async fn get_as_json<R, Q>(&self, object: &ObjectId, query: &Q) -> StorageResult<R>
     where
         R: DeserializeOwned,
         Q: serde::Serialize,
     {
+        let url = object.url();
+        let target = self.endpoint_url(&url)?;
         let response = self
-            .client
-            .client
-            .get(url)
-            .query(query)
-            .bearer_auth(self.refresh_token().await?)
-            .send()
-            .await
-            .map_err(super::Error::GcsHttpError)?;
-        let r: super::super::DeserializedResponse<R> = Self::success_response(url, response)
-            .await?
+            .send_with_retry(&url, || self.client.client.get(target.clone()).query(query))
+            .await?;
+        let r: super::super::DeserializedResponse<R> = response
             .json()
             .await
             .map_err(super::Error::GcsHttpError)?;
@@ -143,3 +715,404 @@ impl<T: TokenGenerator> StorageClient<T> {
             .map_err(|err| super::Error::gcs_unexpected_json::<R>(url, err))
     }
 }
+
+/// Encoding set for an object-name path segment: C0 controls and the reserved
+/// characters that break URL parsing, plus `/` so an object name with embedded
+/// slashes stays a single `o/<name>` segment rather than extra path components.
+const OBJECT_NAME_ENCODE: &percent_encoding::AsciiSet = &percent_encoding::CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'<')
+    .add(b'>')
+    .add(b'#')
+    .add(b'?')
+    .add(b'{')
+    .add(b'}')
+    .add(b'/');
+
+/// A bucket + object name pair. Use this instead of hand-building URLs so that
+/// object names containing slashes, spaces, `#`, `?` and other reserved
+/// characters are percent-encoded correctly rather than producing malformed
+/// requests or spurious 404s.
+#[derive(Debug, Clone)]
+pub(super) struct ObjectId {
+    bucket: String,
+    name: String,
+}
+
+impl ObjectId {
+    pub fn new(bucket: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            name: name.into(),
+        }
+    }
+
+    /// Builds the JSON-API object URL against the default endpoint, percent
+    /// encoding the object name. The endpoint is rewritten per-request by
+    /// [`StorageClient::endpoint_url`], so only the path needs to be correct here.
+    fn url(&self) -> String {
+        format!(
+            "{DEFAULT_ENDPOINT}/storage/v1/b/{}/o/{}",
+            self.bucket,
+            percent_encoding::utf8_percent_encode(&self.name, OBJECT_NAME_ENCODE)
+        )
+    }
+}
+
+/// Canonical host used when signing and when assembling the returned URL.
+const SIGNING_HOST: &str = "storage.googleapis.com";
+
+/// Encoding set for signed-URL query values: RFC 3986 unreserved characters
+/// (`A-Z a-z 0-9 - . _ ~`) are left as-is, everything else is percent-encoded.
+const SIGNING_ENCODE: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Encoding set for the signed-URL canonical path. Identical to
+/// [`SIGNING_ENCODE`] but additionally leaves `/` unencoded: GCS V4 signing
+/// requires forward slashes in the object name to survive verbatim (the
+/// reference signers use `safe="/~"`), otherwise foldered objects 403.
+const SIGNING_PATH_ENCODE: &percent_encoding::AsciiSet = &SIGNING_ENCODE.remove(b'/');
+
+/// Signs GCS V4 URLs with a service account's RSA private key, handing out
+/// time-limited links for GET/PUT/DELETE that carry no bearer token. Construct
+/// one from the same PKCS#8 key material the [`TokenGenerator`] holds.
+#[derive(Debug)]
+pub(super) struct UrlSigner {
+    client_email: String,
+    key_pair: ring::signature::RsaKeyPair,
+}
+
+impl UrlSigner {
+    /// Builds a signer from the service account `client_email` and its PKCS#8
+    /// (DER) private key.
+    pub fn new(client_email: impl Into<String>, pkcs8_der: &[u8]) -> StorageResult<Self> {
+        let key_pair = ring::signature::RsaKeyPair::from_pkcs8(pkcs8_der)
+            .map_err(|err| super::Error::gcs_unexpected_response_error("sign_url", err.to_string()))?;
+        Ok(Self {
+            client_email: client_email.into(),
+            key_pair,
+        })
+    }
+
+    /// Produces a V4 signed URL for `method` (`GET`, `PUT`, `DELETE`, …) against
+    /// `object` in `bucket`, valid for `expires` from `now`. `expires` must not
+    /// exceed 7 days, which is the GCS limit.
+    pub fn sign_url(
+        &self,
+        bucket: &str,
+        object: &str,
+        method: &str,
+        now: std::time::SystemTime,
+        expires: std::time::Duration,
+    ) -> StorageResult<String> {
+        const MAX_EXPIRES: u64 = 7 * 24 * 60 * 60;
+        let expires_secs = expires.as_secs();
+        if expires_secs > MAX_EXPIRES {
+            return Err(super::Error::gcs_unexpected_response_error(
+                "sign_url",
+                "X-Goog-Expires must be at most 7 days".to_owned(),
+            ));
+        }
+
+        let (timestamp, date) = Self::format_time(now);
+        let credential_scope = format!("{date}/auto/storage/goog4_request");
+        let credential = format!("{}/{}", self.client_email, credential_scope);
+
+        let canonical_path = Self::canonical_path(bucket, object);
+        let canonical_query = Self::canonical_query(&credential, &timestamp, expires_secs);
+        let canonical_request = Self::canonical_request(method, &canonical_path, &canonical_query);
+        let string_to_sign =
+            Self::string_to_sign(&timestamp, &credential_scope, &canonical_request);
+
+        let mut signature = vec![0u8; self.key_pair.public().modulus_len()];
+        self.key_pair
+            .sign(
+                &ring::signature::RSA_PKCS1_SHA256,
+                &ring::rand::SystemRandom::new(),
+                string_to_sign.as_bytes(),
+                &mut signature,
+            )
+            .map_err(|err| super::Error::gcs_unexpected_response_error("sign_url", err.to_string()))?;
+
+        Ok(format!(
+            "https://{SIGNING_HOST}{canonical_path}?{canonical_query}&X-Goog-Signature={}",
+            hex_encode(&signature)
+        ))
+    }
+
+    /// Canonical resource path: `/<bucket>/<object>` with the object name
+    /// percent-encoded but forward slashes left intact.
+    fn canonical_path(bucket: &str, object: &str) -> String {
+        format!(
+            "/{}/{}",
+            bucket,
+            percent_encoding::utf8_percent_encode(object, SIGNING_PATH_ENCODE)
+        )
+    }
+
+    /// Canonical query string: the V4 query parameters, each percent-encoded and
+    /// sorted by encoded name.
+    fn canonical_query(credential: &str, timestamp: &str, expires_secs: u64) -> String {
+        let mut params = [
+            ("X-Goog-Algorithm", "GOOG4-RSA-SHA256".to_owned()),
+            ("X-Goog-Credential", credential.to_owned()),
+            ("X-Goog-Date", timestamp.to_owned()),
+            ("X-Goog-Expires", expires_secs.to_string()),
+            ("X-Goog-SignedHeaders", "host".to_owned()),
+        ];
+        params.sort_by(|a, b| a.0.cmp(b.0));
+        params
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "{}={}",
+                    percent_encoding::utf8_percent_encode(name, SIGNING_ENCODE),
+                    percent_encoding::utf8_percent_encode(value, SIGNING_ENCODE)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Assembles the canonical request from the verb, path and query, with the
+    /// fixed `host` canonical header and an unsigned payload.
+    fn canonical_request(method: &str, canonical_path: &str, canonical_query: &str) -> String {
+        format!(
+            "{method}\n{canonical_path}\n{canonical_query}\nhost:{SIGNING_HOST}\n\nhost\nUNSIGNED-PAYLOAD"
+        )
+    }
+
+    /// Builds the string-to-sign from the SHA-256 hash of the canonical request.
+    fn string_to_sign(timestamp: &str, credential_scope: &str, canonical_request: &str) -> String {
+        let hashed_request = hex_encode(
+            ring::digest::digest(&ring::digest::SHA256, canonical_request.as_bytes()).as_ref(),
+        );
+        format!("GOOG4-RSA-SHA256\n{timestamp}\n{credential_scope}\n{hashed_request}")
+    }
+
+    /// Formats `time` as the `YYYYMMDDTHHMMSSZ` timestamp and `YYYYMMDD` date
+    /// required by V4 signing, both in UTC.
+    fn format_time(time: std::time::SystemTime) -> (String, String) {
+        let when = chrono::DateTime::<chrono::Utc>::from(time);
+        (
+            when.format("%Y%m%dT%H%M%SZ").to_string(),
+            when.format("%Y%m%d").to_string(),
+        )
+    }
+}
+
+/// Lowercase hex encoding, used for the canonical-request hash and the signature.
+fn hex_encode(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut acc, b| {
+        let _ = write!(acc, "{b:02x}");
+        acc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    // A throwaway 2048-bit RSA key (PKCS#8 DER) used only to exercise signing.
+    const TEST_PKCS8: &[u8] = &[
+        48, 130, 4, 189, 2, 1, 0, 48, 13, 6, 9, 42, 134, 72, 134, 247, 13, 1, 1, 1, 5, 0, 4, 130,
+        4, 167, 48, 130, 4, 163, 2, 1, 0, 2, 130, 1, 1, 0, 172, 213, 216, 139, 196, 107, 226, 61,
+        219, 218, 206, 67, 240, 192, 58, 90, 115, 86, 118, 62, 247, 229, 20, 136, 207, 65, 89,
+        202, 147, 37, 181, 238, 242, 148, 230, 148, 5, 66, 187, 99, 241, 130, 181, 245, 121, 166,
+        188, 3, 205, 23, 22, 192, 68, 34, 79, 222, 149, 98, 93, 169, 176, 232, 168, 31, 36, 215,
+        42, 190, 24, 125, 58, 83, 236, 43, 181, 234, 169, 203, 160, 40, 30, 76, 226, 65, 255, 129,
+        115, 96, 207, 29, 181, 45, 74, 136, 105, 78, 74, 201, 200, 9, 42, 184, 138, 145, 236, 98,
+        205, 135, 186, 227, 106, 97, 62, 70, 202, 98, 10, 130, 189, 159, 158, 231, 88, 243, 30,
+        64, 239, 178, 192, 85, 94, 214, 102, 89, 254, 171, 242, 25, 66, 123, 74, 60, 205, 22, 64,
+        172, 99, 253, 97, 110, 18, 182, 237, 163, 174, 202, 224, 180, 62, 84, 211, 0, 210, 184,
+        40, 50, 65, 206, 153, 119, 248, 231, 137, 38, 89, 167, 203, 5, 36, 43, 22, 102, 247, 155,
+        42, 135, 123, 248, 107, 249, 88, 248, 81, 121, 141, 132, 42, 19, 224, 20, 147, 205, 244,
+        217, 4, 107, 58, 157, 45, 94, 79, 131, 92, 188, 69, 220, 157, 43, 187, 205, 161, 5, 170,
+        239, 157, 214, 161, 16, 75, 67, 3, 40, 202, 70, 76, 181, 48, 19, 207, 119, 102, 171, 65,
+        98, 237, 35, 39, 70, 185, 76, 13, 40, 77, 66, 150, 15, 2, 3, 1, 0, 1, 2, 130, 1, 0, 17, 8,
+        22, 94, 238, 60, 17, 95, 54, 39, 70, 223, 57, 104, 191, 247, 87, 131, 249, 130, 181, 86,
+        167, 140, 223, 27, 154, 2, 106, 142, 8, 157, 35, 136, 218, 123, 83, 84, 184, 76, 72, 212,
+        48, 35, 203, 89, 75, 186, 203, 126, 228, 120, 226, 221, 242, 15, 240, 68, 70, 186, 232,
+        239, 129, 173, 187, 254, 208, 187, 245, 217, 127, 247, 114, 212, 251, 50, 196, 222, 92,
+        223, 28, 137, 72, 7, 61, 64, 159, 152, 54, 118, 20, 208, 172, 150, 106, 91, 125, 145, 138,
+        122, 248, 198, 65, 176, 37, 56, 11, 247, 232, 73, 238, 112, 60, 103, 169, 28, 59, 49, 66,
+        8, 20, 235, 200, 84, 171, 135, 182, 76, 96, 206, 105, 64, 2, 177, 253, 83, 198, 4, 109, 77,
+        26, 90, 169, 95, 122, 153, 248, 245, 157, 191, 235, 250, 230, 118, 199, 220, 27, 65, 32,
+        97, 120, 19, 50, 230, 56, 248, 93, 254, 24, 84, 113, 60, 159, 8, 200, 123, 124, 219, 66,
+        112, 207, 148, 5, 108, 82, 72, 207, 219, 115, 53, 111, 255, 244, 188, 169, 20, 126, 149,
+        111, 217, 203, 43, 33, 46, 27, 156, 109, 79, 8, 68, 29, 215, 42, 139, 123, 187, 145, 38,
+        96, 129, 127, 153, 205, 29, 0, 90, 157, 246, 186, 86, 34, 3, 186, 51, 88, 203, 183, 254,
+        43, 31, 40, 207, 3, 98, 79, 77, 81, 26, 181, 230, 206, 127, 73, 98, 187, 153, 2, 129, 129,
+        0, 212, 174, 82, 7, 87, 6, 133, 8, 139, 252, 248, 87, 34, 52, 66, 233, 217, 136, 33, 149,
+        153, 101, 89, 40, 136, 197, 95, 141, 183, 115, 73, 205, 53, 43, 46, 165, 175, 150, 104, 75,
+        172, 124, 67, 127, 204, 231, 19, 39, 185, 58, 35, 144, 67, 81, 100, 9, 159, 249, 95, 59,
+        14, 101, 233, 87, 178, 89, 88, 189, 31, 170, 89, 59, 179, 197, 255, 206, 87, 161, 181, 126,
+        213, 10, 251, 137, 174, 220, 130, 76, 17, 174, 200, 149, 100, 125, 221, 247, 6, 73, 112,
+        78, 128, 201, 193, 134, 13, 52, 101, 200, 105, 15, 228, 106, 58, 62, 248, 98, 217, 1, 37,
+        32, 123, 106, 207, 186, 254, 26, 181, 121, 2, 129, 129, 0, 208, 9, 226, 3, 164, 230, 102,
+        32, 6, 151, 51, 38, 37, 58, 111, 233, 196, 255, 74, 253, 144, 166, 54, 3, 32, 238, 147, 78,
+        7, 169, 188, 202, 225, 92, 91, 136, 216, 222, 205, 121, 196, 245, 118, 116, 109, 219, 86,
+        27, 138, 252, 255, 0, 209, 27, 228, 110, 230, 173, 223, 12, 198, 70, 136, 134, 128, 185,
+        95, 238, 179, 73, 107, 209, 35, 84, 69, 9, 217, 205, 166, 254, 7, 112, 251, 30, 69, 110,
+        64, 75, 176, 79, 27, 110, 185, 64, 106, 11, 114, 215, 38, 247, 44, 121, 166, 239, 24, 145,
+        150, 160, 27, 252, 207, 191, 27, 23, 245, 11, 52, 19, 211, 227, 131, 188, 210, 87, 217, 83,
+        109, 199, 2, 129, 128, 30, 169, 247, 135, 185, 99, 102, 64, 226, 54, 25, 244, 46, 41, 76,
+        208, 124, 157, 165, 1, 245, 184, 232, 14, 241, 17, 76, 2, 153, 197, 148, 137, 114, 182, 4,
+        38, 189, 87, 57, 56, 87, 233, 30, 174, 73, 115, 179, 142, 81, 165, 113, 69, 54, 127, 128,
+        165, 230, 155, 196, 192, 54, 6, 57, 139, 124, 90, 103, 113, 245, 35, 209, 115, 203, 42,
+        172, 4, 43, 28, 84, 151, 177, 205, 192, 144, 140, 86, 156, 174, 173, 9, 255, 63, 146, 218,
+        150, 235, 43, 203, 224, 214, 96, 185, 54, 30, 198, 190, 156, 66, 53, 235, 165, 170, 47, 53,
+        175, 8, 73, 115, 97, 207, 250, 27, 95, 81, 253, 155, 10, 57, 2, 129, 128, 40, 230, 216,
+        133, 232, 228, 215, 141, 82, 94, 101, 211, 9, 28, 189, 38, 19, 242, 49, 119, 58, 250, 66,
+        194, 72, 182, 63, 69, 231, 33, 203, 25, 132, 71, 89, 67, 197, 135, 192, 148, 65, 82, 21,
+        80, 252, 204, 83, 216, 164, 113, 235, 92, 232, 191, 248, 32, 137, 192, 3, 139, 138, 108,
+        17, 169, 136, 35, 106, 38, 15, 31, 207, 79, 214, 7, 90, 111, 126, 95, 253, 134, 18, 70,
+        119, 72, 211, 83, 194, 214, 94, 186, 158, 229, 29, 12, 250, 12, 120, 46, 174, 221, 135,
+        227, 255, 27, 33, 20, 118, 173, 209, 242, 217, 145, 116, 45, 56, 163, 84, 64, 213, 182,
+        244, 142, 170, 56, 85, 101, 30, 191, 2, 129, 129, 0, 183, 8, 194, 240, 109, 211, 156, 72,
+        126, 33, 21, 23, 87, 132, 185, 37, 181, 144, 98, 42, 142, 147, 230, 164, 232, 179, 92, 22,
+        28, 143, 235, 158, 34, 253, 114, 130, 136, 56, 211, 127, 207, 174, 203, 176, 119, 211, 175,
+        140, 167, 201, 249, 147, 202, 218, 242, 179, 79, 105, 29, 250, 113, 63, 153, 215, 69, 226,
+        208, 161, 2, 170, 69, 15, 7, 83, 169, 144, 84, 239, 42, 128, 3, 53, 221, 227, 88, 151, 120,
+        237, 47, 141, 134, 156, 142, 87, 96, 243, 135, 8, 86, 224, 64, 149, 116, 216, 16, 240, 244,
+        126, 207, 236, 135, 226, 198, 3, 175, 59, 42, 230, 65, 25, 56, 68, 225, 177, 217, 140, 165,
+        15,
+    ];
+
+    const EMAIL: &str = "test@example.iam.gserviceaccount.com";
+    // A single object name exercising every character the encoders must escape.
+    const OBJECT: &str = "a/b c#d?e";
+
+    fn fixed_now() -> std::time::SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_600_000_000) // 2020-09-13T12:26:40Z
+    }
+
+    #[test]
+    fn object_id_url_percent_encodes_reserved_characters() {
+        let url = ObjectId::new("my-bucket", OBJECT).url();
+        assert_eq!(
+            url,
+            "https://storage.googleapis.com/storage/v1/b/my-bucket/o/a%2Fb%20c%23d%3Fe"
+        );
+    }
+
+    #[test]
+    fn signing_canonical_path_keeps_slashes() {
+        assert_eq!(
+            UrlSigner::canonical_path("my-bucket", OBJECT),
+            "/my-bucket/a/b%20c%23d%3Fe"
+        );
+    }
+
+    #[test]
+    fn signing_canonical_request_is_stable() {
+        let credential = format!("{EMAIL}/20200913/auto/storage/goog4_request");
+        let path = UrlSigner::canonical_path("my-bucket", OBJECT);
+        let query = UrlSigner::canonical_query(&credential, "20200913T122640Z", 3600);
+        assert_eq!(
+            query,
+            "X-Goog-Algorithm=GOOG4-RSA-SHA256\
+             &X-Goog-Credential=test%40example.iam.gserviceaccount.com%2F20200913%2Fauto%2Fstorage%2Fgoog4_request\
+             &X-Goog-Date=20200913T122640Z\
+             &X-Goog-Expires=3600\
+             &X-Goog-SignedHeaders=host"
+        );
+        let canonical_request = UrlSigner::canonical_request("GET", &path, &query);
+        assert_eq!(
+            canonical_request,
+            "GET\n/my-bucket/a/b%20c%23d%3Fe\n\
+             X-Goog-Algorithm=GOOG4-RSA-SHA256\
+             &X-Goog-Credential=test%40example.iam.gserviceaccount.com%2F20200913%2Fauto%2Fstorage%2Fgoog4_request\
+             &X-Goog-Date=20200913T122640Z&X-Goog-Expires=3600&X-Goog-SignedHeaders=host\n\
+             host:storage.googleapis.com\n\nhost\nUNSIGNED-PAYLOAD"
+        );
+        let string_to_sign = UrlSigner::string_to_sign(
+            "20200913T122640Z",
+            "20200913/auto/storage/goog4_request",
+            &canonical_request,
+        );
+        let (prefix, hash) = string_to_sign
+            .rsplit_once('\n')
+            .expect("string-to-sign has a trailing hash line");
+        assert_eq!(
+            prefix,
+            "GOOG4-RSA-SHA256\n20200913T122640Z\n20200913/auto/storage/goog4_request"
+        );
+        assert_eq!(hash.len(), 64);
+        assert!(hash.bytes().all(|b| b.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn sign_url_produces_a_well_formed_signed_url() {
+        let signer = UrlSigner::new(EMAIL, TEST_PKCS8).expect("valid PKCS#8 key");
+        let url = signer
+            .sign_url("my-bucket", OBJECT, "GET", fixed_now(), Duration::from_secs(3600))
+            .expect("signing succeeds");
+
+        let (base, signature) = url
+            .rsplit_once("&X-Goog-Signature=")
+            .expect("signed URL carries a signature");
+        assert!(base.starts_with("https://storage.googleapis.com/my-bucket/a/b%20c%23d%3Fe?"));
+        // 2048-bit key -> 256-byte signature -> 512 hex characters.
+        assert_eq!(signature.len(), 512);
+        assert!(signature.bytes().all(|b| b.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn sign_url_rejects_expiry_over_seven_days() {
+        let signer = UrlSigner::new(EMAIL, TEST_PKCS8).expect("valid PKCS#8 key");
+        let too_long = Duration::from_secs(7 * 24 * 60 * 60 + 1);
+        assert!(signer
+            .sign_url("my-bucket", OBJECT, "GET", fixed_now(), too_long)
+            .is_err());
+    }
+
+    #[test]
+    fn retry_after_parses_seconds_and_dates() {
+        let now = UNIX_EPOCH + Duration::from_secs(1000);
+        assert_eq!(
+            RetryPolicy::parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            RetryPolicy::parse_retry_after("Thu, 01 Jan 1970 00:17:40 GMT", now),
+            Some(Duration::from_secs(60))
+        );
+        // A date already in the past falls back to the computed backoff.
+        assert_eq!(
+            RetryPolicy::parse_retry_after("Thu, 01 Jan 1970 00:00:10 GMT", now),
+            None
+        );
+        assert_eq!(RetryPolicy::parse_retry_after("not-a-date", now), None);
+    }
+
+    #[test]
+    fn backoff_is_bounded_by_base_and_cap() {
+        let policy = RetryPolicy::new(
+            5,
+            Duration::from_millis(500),
+            Duration::from_secs(32),
+        );
+        // attempt 0 is capped at `base`; large attempts saturate to `cap`.
+        for _ in 0..100 {
+            assert!(policy.backoff(0) <= Duration::from_millis(500));
+            assert!(policy.backoff(100) <= Duration::from_secs(32));
+        }
+    }
+
+    #[test]
+    fn only_transient_statuses_are_retryable() {
+        use reqwest::StatusCode;
+        assert!(RetryPolicy::is_retryable(StatusCode::REQUEST_TIMEOUT));
+        assert!(RetryPolicy::is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(RetryPolicy::is_retryable(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!RetryPolicy::is_retryable(StatusCode::NOT_FOUND));
+        assert!(!RetryPolicy::is_retryable(StatusCode::BAD_REQUEST));
+    }
+}